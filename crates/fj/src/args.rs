@@ -1,81 +1,288 @@
-use std::{num::ParseFloatError, path::PathBuf, str::FromStr};
+use std::{ffi::OsString, fmt, num::ParseFloatError, path::PathBuf, str::FromStr};
 
 use fj_interop::{InvalidTolerance, Tolerance};
 use fj_math::Scalar;
 
+use crate::{
+    diagnostic::{Diagnostic, Snippet},
+    export::ExportFormat,
+    report::Report,
+};
+
 /// Standardized CLI for Fornjot models.
 ///
 /// Provides a unified interface for exporting and validating models.
 /// Used by example models and testing infrastructure.
+///
+/// Constructed via [`Args::parse`], which validates `--tolerance` on top of
+/// clap's own parsing, so a bad tolerance renders as a [`Report`] (with its
+/// code, help text, and caret snippet) instead of clap's generic error.
+#[derive(Debug)]
+pub struct Args {
+    /// Export model to this path
+    pub export: Option<PathBuf>,
+
+    /// Format to export the model as
+    pub format: Option<ExportFormat>,
+
+    /// How much the export can deviate from the original model
+    pub tolerance: Option<Tolerance>,
+
+    /// How to handle validation errors found in the model
+    pub validation: ValidationMode,
+
+    /// Maximum number of validation issues to process before aborting
+    pub max_errors: Option<usize>,
+
+    /// Column width to wrap diagnostic output at
+    pub diagnostic_width: usize,
+}
+
+/// The command-line arguments as clap parses them, before `--tolerance` is
+/// validated.
+///
+/// `tolerance` is kept as a raw `String` here rather than parsed through
+/// clap's `value_parser`, because a `value_parser` failure is consumed by
+/// clap's own error printer before [`Args::parse`] ever gets a chance to
+/// render it as a [`Report`].
 #[derive(clap::Parser, Debug)]
 #[command(author, version, about, long_about = None)]
-pub struct Args {
+struct RawArgs {
     /// Export model to this path
+    ///
+    /// Pass `-` to write the export to stdout instead of a file.
     #[arg(short, long, value_name = "PATH", help = "Path to export the model")]
-    pub export: Option<PathBuf>,
+    export: Option<PathBuf>,
+
+    /// Format to export the model as
+    ///
+    /// Inferred from the export path's extension, if omitted. Required when
+    /// exporting to stdout (`--export -`), since there is no path to infer it
+    /// from. If given together with a path that has a recognized extension,
+    /// the two must agree.
+    #[arg(long, value_name = "FORMAT", help = "Format to export the model as")]
+    format: Option<ExportFormat>,
 
     /// How much the export can deviate from the original model
+    #[arg(short, long, value_name = "TOLERANCE", help = "Tolerance for export deviation (e.g. 0.001)")]
+    tolerance: Option<String>,
+
+    /// How to handle validation errors found in the model
     #[arg(
-        short,
         long,
-        value_parser = parse_tolerance,
-        help = "Tolerance for export deviation (e.g. 0.001)"
+        value_name = "MODE",
+        default_value_t = ValidationMode::Strict,
+        help = "How to handle validation errors (strict, warn, or off)"
     )]
-    pub tolerance: Option<Tolerance>,
+    validation: ValidationMode,
+
+    /// Maximum number of validation issues to process before aborting
+    ///
+    /// Has no effect in `--validation off`, or in `--validation strict`
+    /// (which reports every issue up front rather than counting them).
+    #[arg(long, value_name = "N", help = "Max validation issues to process before aborting")]
+    max_errors: Option<usize>,
+
+    /// Column width to wrap diagnostic output at
+    #[arg(
+        long,
+        value_name = "COLS",
+        default_value_t = default_diagnostic_width(),
+        help = "Column width to wrap diagnostic output at"
+    )]
+    diagnostic_width: usize,
+}
+
+/// Detect the terminal width, falling back to a sensible default.
+fn default_diagnostic_width() -> usize {
+    terminal_size::terminal_size()
+        .map(|(terminal_size::Width(width), _)| width as usize)
+        .unwrap_or(80)
+}
+
+/// How to handle validation errors found in the model.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum ValidationMode {
+    /// Abort if there are any validation issues, regardless of their
+    /// severity, reporting all of them at once
+    Strict,
+    /// Log issues and proceed, aborting only on ones marked fatal
+    ///
+    /// `fj_core` doesn't yet classify individual checks by severity, so
+    /// every issue is currently tolerated; pass `--max-errors` to still cut
+    /// a run short.
+    Warn,
+    /// Skip validation entirely
+    Off,
+}
 
-    /// Ignore validation errors
-    #[arg(short, long, help = "Ignore validation errors during export")]
-    pub ignore_validation: bool,
+impl fmt::Display for ValidationMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self {
+            Self::Strict => "strict",
+            Self::Warn => "warn",
+            Self::Off => "off",
+        };
+        write!(f, "{mode}")
+    }
 }
 
 impl Args {
     /// Parse the command-line arguments.
     ///
-    /// Convenience method that saves the caller from having to import the
-    /// `clap::Parser` trait.
+    /// Clap-level errors (missing values, unknown flags, `--help`, ...) are
+    /// printed and exit the process the usual way. A bad `--tolerance`,
+    /// however, is rendered as a [`Report`] before exiting, so it gets the
+    /// same code/help/snippet treatment as any other diagnostic.
     pub fn parse() -> Self {
-        <Self as clap::Parser>::parse()
+        Self::try_parse_from(std::env::args_os()).unwrap_or_else(|report| {
+            eprintln!("{report}");
+            std::process::exit(1);
+        })
+    }
+
+    fn try_parse_from<I, T>(args: I) -> Result<Self, Report>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let raw = <RawArgs as clap::Parser>::parse_from(args);
+        let tolerance = raw.tolerance.map(|input| parse_tolerance(&input)).transpose()?;
+
+        Ok(Self {
+            export: raw.export,
+            format: raw.format,
+            tolerance,
+            validation: raw.validation,
+            max_errors: raw.max_errors,
+            diagnostic_width: raw.diagnostic_width,
+        })
     }
 }
 
 /// Parse a string into a Tolerance value.
 fn parse_tolerance(input: &str) -> Result<Tolerance, ArgsError> {
-    let tolerance = f64::from_str(input)
-        .map_err(ArgsError::ParseTolerance)?;
+    let tolerance = f64::from_str(input).map_err(|source| ArgsError::ParseTolerance {
+        input: input.to_string(),
+        source,
+    })?;
     let tolerance = Scalar::from_f64(tolerance);
-    Tolerance::from_scalar(tolerance)
-        .map_err(ArgsError::InvalidTolerance)
+    Tolerance::from_scalar(tolerance).map_err(|source| ArgsError::InvalidTolerance {
+        input: input.to_string(),
+        source,
+    })
 }
 
 #[derive(Debug, thiserror::Error)]
 pub enum ArgsError {
-    #[error("Error parsing tolerance: {0}")]
-    ParseTolerance(#[from] ParseFloatError),
+    #[error("Error parsing tolerance `{input}`: {source}")]
+    ParseTolerance {
+        /// The offending `--tolerance` argument
+        input: String,
+        source: ParseFloatError,
+    },
 
-    #[error("Invalid tolerance: {0}")]
-    InvalidTolerance(#[from] InvalidTolerance),
+    #[error("Invalid tolerance `{input}`: {source}")]
+    InvalidTolerance {
+        /// The offending `--tolerance` argument
+        input: String,
+        source: InvalidTolerance,
+    },
+}
+
+impl Diagnostic for ArgsError {
+    fn code(&self) -> &str {
+        match self {
+            Self::ParseTolerance { .. } => "fornjot::parse_tolerance",
+            Self::InvalidTolerance { .. } => "fornjot::invalid_tolerance",
+        }
+    }
+
+    fn help(&self) -> Option<&str> {
+        match self {
+            Self::ParseTolerance { .. } => Some("pass a plain decimal number, e.g. `--tolerance 0.001`"),
+            Self::InvalidTolerance { .. } => Some("pass a tolerance greater than zero"),
+        }
+    }
+
+    fn snippet(&self) -> Option<Snippet> {
+        let input = match self {
+            Self::ParseTolerance { input, .. } | Self::InvalidTolerance { input, .. } => input,
+        };
+
+        Some(Snippet {
+            source: input.clone(),
+            span: 0..input.len(),
+        })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use clap::Parser;
 
     #[test]
     fn test_args_parse_export() {
-        let args = Args::parse_from(&["test", "--export", "foo.step"]);
+        let args = Args::try_parse_from(["test", "--export", "foo.step"]).unwrap();
         assert_eq!(args.export, Some(PathBuf::from("foo.step")));
     }
 
     #[test]
     fn test_args_parse_tolerance() {
-        let args = Args::parse_from(&["test", "--tolerance", "0.01"]);
+        let args = Args::try_parse_from(["test", "--tolerance", "0.01"]).unwrap();
         assert!(args.tolerance.is_some());
     }
 
     #[test]
-    fn test_args_parse_ignore_validation() {
-        let args = Args::parse_from(&["test", "--ignore-validation"]);
-        assert!(args.ignore_validation);
+    fn test_args_parse_tolerance_rejects_garbage() {
+        let result = Args::try_parse_from(["test", "--tolerance", "abc"]);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_parse_tolerance_renders_as_report_with_snippet() {
+        let report: Report = parse_tolerance("abc").unwrap_err().into();
+        let rendered = report.to_string();
+
+        assert!(rendered.contains("fornjot::parse_tolerance"));
+        assert!(rendered.contains("abc"));
+        assert!(rendered.contains('^'));
+        assert!(rendered.contains("pass a plain decimal number"));
+    }
+
+    #[test]
+    fn test_args_parse_validation_mode() {
+        let args = Args::try_parse_from(["test", "--validation", "warn"]).unwrap();
+        assert_eq!(args.validation, ValidationMode::Warn);
+    }
+
+    #[test]
+    fn test_args_default_validation_mode_is_strict() {
+        let args = Args::try_parse_from(["test"]).unwrap();
+        assert_eq!(args.validation, ValidationMode::Strict);
+    }
+
+    #[test]
+    fn test_args_parse_max_errors() {
+        let args = Args::try_parse_from(["test", "--max-errors", "10"]).unwrap();
+        assert_eq!(args.max_errors, Some(10));
+    }
+
+    #[test]
+    fn test_args_parse_format() {
+        let args = Args::try_parse_from(["test", "--format", "3mf"]).unwrap();
+        assert_eq!(args.format, Some(ExportFormat::ThreeMf));
+    }
+
+    #[test]
+    fn test_args_default_format_is_inferred() {
+        let args = Args::try_parse_from(["test", "--export", "foo.stl"]).unwrap();
+        assert_eq!(args.format, None);
+    }
+
+    #[test]
+    fn test_args_parse_diagnostic_width() {
+        let args = Args::try_parse_from(["test", "--diagnostic-width", "120"]).unwrap();
+        assert_eq!(args.diagnostic_width, 120);
     }
 }
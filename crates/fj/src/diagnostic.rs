@@ -0,0 +1,123 @@
+use std::fmt;
+
+/// A diagnostic protocol for errors, inspired by `miette`'s `Diagnostic`.
+///
+/// Implementing this (in addition to [`std::error::Error`]) lets an error
+/// carry a stable, machine-readable code, an optional suggestion for how to
+/// fix it, and a severity, all of which [`Report`](crate::report::Report)
+/// picks up when the error is first wrapped.
+pub trait Diagnostic: std::error::Error {
+    /// A stable, machine-readable identifier, e.g. `fornjot::empty_model`.
+    fn code(&self) -> &str;
+
+    /// A human-readable suggestion for resolving the diagnostic.
+    fn help(&self) -> Option<&str> {
+        None
+    }
+
+    /// How severe the diagnostic is.
+    fn severity(&self) -> Severity {
+        Severity::Error
+    }
+
+    /// A source snippet to render beneath the diagnostic, with a caret
+    /// pointing at the offending span.
+    fn snippet(&self) -> Option<Snippet> {
+        None
+    }
+}
+
+/// How severe a [`Diagnostic`] is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Severity {
+    /// The operation failed and could not proceed.
+    Error,
+    /// The operation proceeded, but something is worth drawing attention to.
+    Warning,
+    /// Informational; neither an error nor necessarily a problem.
+    Advice,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let label = match self {
+            Self::Error => "error",
+            Self::Warning => "warning",
+            Self::Advice => "advice",
+        };
+        write!(f, "{label}")
+    }
+}
+
+/// A source snippet and the span within it that a [`Diagnostic`] applies to.
+#[derive(Clone, Debug)]
+pub struct Snippet {
+    /// The full source text the diagnostic applies to, e.g. a CLI argument.
+    pub source: String,
+
+    /// The byte range within `source` to underline with a caret.
+    pub span: std::ops::Range<usize>,
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.span.start.min(self.source.len());
+        let end = self.span.end.clamp(start, self.source.len());
+
+        writeln!(f, "    {}", self.source)?;
+        write!(f, "    {}{}", " ".repeat(start), "^".repeat((end - start).max(1)))
+    }
+}
+
+/// Greedily wrap `text` to `width` columns, preserving existing line breaks.
+///
+/// This is a small, dependency-free stand-in for a proper text-wrapping
+/// crate; it's only used to keep diagnostic output readable in narrow
+/// terminals, not for typesetting.
+pub(crate) fn wrap(text: &str, width: usize) -> String {
+    let width = width.max(1);
+    let mut wrapped = String::new();
+
+    for (i, line) in text.lines().enumerate() {
+        if i > 0 {
+            wrapped.push('\n');
+        }
+
+        let mut column = 0;
+        for (j, word) in line.split(' ').enumerate() {
+            if j > 0 {
+                if column + 1 + word.len() > width {
+                    wrapped.push('\n');
+                    column = 0;
+                } else {
+                    wrapped.push(' ');
+                    column += 1;
+                }
+            }
+            wrapped.push_str(word);
+            column += word.len();
+        }
+    }
+
+    wrapped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_preserves_short_lines() {
+        assert_eq!(wrap("short line", 80), "short line");
+    }
+
+    #[test]
+    fn test_wrap_breaks_long_lines_at_width() {
+        assert_eq!(wrap("one two three four five", 10), "one two\nthree four\nfive");
+    }
+
+    #[test]
+    fn test_wrap_preserves_existing_line_breaks() {
+        assert_eq!(wrap("first\nsecond", 80), "first\nsecond");
+    }
+}
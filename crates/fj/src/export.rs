@@ -0,0 +1,337 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use fj_interop::Triangle;
+use fj_math::Point;
+
+use crate::diagnostic::Diagnostic;
+
+/// Export a triangle mesh to a file.
+///
+/// The export format is inferred from `path`'s extension. To export in a
+/// specific format regardless of the path, or to stream to stdout, use
+/// [`export_with_format`].
+pub fn export(triangles: &[Triangle], path: &Path) -> Result<(), Error> {
+    export_with_format(triangles, path, None)
+}
+
+/// Export a triangle mesh to a file, optionally pinning the export format.
+///
+/// If `format` is `None`, it is inferred from `path`'s extension. If both are
+/// given, they must agree, or [`Error::FormatMismatch`] is returned. Passing
+/// `-` as `path` writes the export to stdout instead of a file, which is
+/// useful for piping into another tool; in that case, `format` must be given
+/// explicitly, since there is no path to infer it from.
+pub fn export_with_format(
+    triangles: &[Triangle],
+    path: &Path,
+    format: Option<ExportFormat>,
+) -> Result<(), Error> {
+    let inferred = ExportFormat::from_extension(path);
+    let format = match (format, inferred) {
+        (Some(format), None) => format,
+        (Some(format), Some(inferred)) if format == inferred => format,
+        (Some(requested), Some(inferred)) => {
+            return Err(Error::FormatMismatch { requested, inferred });
+        }
+        (None, Some(inferred)) => inferred,
+        (None, None) => {
+            return Err(Error::UnknownFormat {
+                path: path.to_path_buf(),
+            });
+        }
+    };
+
+    let writer = writer_for(format);
+
+    if path == Path::new("-") {
+        let stdout = io::stdout();
+        return writer.write(triangles, &mut stdout.lock());
+    }
+
+    let file = File::create(path)?;
+    writer.write(triangles, &mut BufWriter::new(file))
+}
+
+/// The file formats that Fornjot can export a triangle mesh to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, clap::ValueEnum)]
+pub enum ExportFormat {
+    /// The STL format
+    Stl,
+    /// The Wavefront OBJ format
+    Obj,
+    /// The 3D Manufacturing Format
+    #[value(name = "3mf")]
+    ThreeMf,
+    /// The Stanford PLY format
+    Ply,
+}
+
+impl ExportFormat {
+    /// Infer the export format from a file path's extension.
+    ///
+    /// Returns `None` if the path has no extension, or the extension isn't
+    /// recognized by any registered [`MeshWriter`].
+    pub fn from_extension(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?;
+        WRITERS
+            .iter()
+            .find(|writer| writer.extension().eq_ignore_ascii_case(extension))
+            .map(|writer| writer.format())
+    }
+}
+
+/// Writes a triangle mesh to an output stream, in a specific file format.
+///
+/// New formats are supported by implementing this trait and adding an entry
+/// to [`WRITERS`]; no changes to [`Instance`](crate::Instance) are needed.
+pub trait MeshWriter {
+    /// The [`ExportFormat`] this writer produces.
+    fn format(&self) -> ExportFormat;
+
+    /// The file extension conventionally used for this format, without the
+    /// leading dot.
+    fn extension(&self) -> &str;
+
+    /// Write `triangles` to `out`.
+    fn write(&self, triangles: &[Triangle], out: &mut dyn Write) -> Result<(), Error>;
+}
+
+/// The mesh writers Fornjot ships with, keyed by the format they produce.
+const WRITERS: &[&dyn MeshWriter] = &[&StlWriter, &ObjWriter, &ThreeMfWriter, &PlyWriter];
+
+fn writer_for(format: ExportFormat) -> &'static dyn MeshWriter {
+    WRITERS
+        .iter()
+        .find(|writer| writer.format() == format)
+        .copied()
+        .expect("a writer is registered for every `ExportFormat` variant")
+}
+
+fn coords(point: &Point<3>) -> [f64; 3] {
+    point.coords.components.map(|coord| coord.into_f64())
+}
+
+struct StlWriter;
+
+impl MeshWriter for StlWriter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Stl
+    }
+
+    fn extension(&self) -> &str {
+        "stl"
+    }
+
+    fn write(&self, triangles: &[Triangle], out: &mut dyn Write) -> Result<(), Error> {
+        writeln!(out, "solid fornjot")?;
+
+        for triangle in triangles {
+            let [a, b, c] = triangle.points.map(|point| coords(&point));
+            let normal = coords(&triangle.normal.into());
+
+            writeln!(out, "facet normal {} {} {}", normal[0], normal[1], normal[2])?;
+            writeln!(out, "    outer loop")?;
+            for vertex in [a, b, c] {
+                writeln!(out, "        vertex {} {} {}", vertex[0], vertex[1], vertex[2])?;
+            }
+            writeln!(out, "    endloop")?;
+            writeln!(out, "endfacet")?;
+        }
+
+        writeln!(out, "endsolid fornjot")?;
+
+        Ok(())
+    }
+}
+
+struct ObjWriter;
+
+impl MeshWriter for ObjWriter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Obj
+    }
+
+    fn extension(&self) -> &str {
+        "obj"
+    }
+
+    fn write(&self, triangles: &[Triangle], out: &mut dyn Write) -> Result<(), Error> {
+        for triangle in triangles {
+            for point in triangle.points {
+                let [x, y, z] = coords(&point);
+                writeln!(out, "v {x} {y} {z}")?;
+            }
+        }
+
+        for (i, _) in triangles.iter().enumerate() {
+            let base = i * 3;
+            writeln!(out, "f {} {} {}", base + 1, base + 2, base + 3)?;
+        }
+
+        Ok(())
+    }
+}
+
+struct ThreeMfWriter;
+
+impl MeshWriter for ThreeMfWriter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::ThreeMf
+    }
+
+    fn extension(&self) -> &str {
+        "3mf"
+    }
+
+    fn write(&self, triangles: &[Triangle], out: &mut dyn Write) -> Result<(), Error> {
+        threemf::write(out, triangles).map_err(Error::ThreeMf)
+    }
+}
+
+struct PlyWriter;
+
+impl MeshWriter for PlyWriter {
+    fn format(&self) -> ExportFormat {
+        ExportFormat::Ply
+    }
+
+    fn extension(&self) -> &str {
+        "ply"
+    }
+
+    fn write(&self, triangles: &[Triangle], out: &mut dyn Write) -> Result<(), Error> {
+        writeln!(out, "ply")?;
+        writeln!(out, "format ascii 1.0")?;
+        writeln!(out, "element vertex {}", triangles.len() * 3)?;
+        writeln!(out, "property float x")?;
+        writeln!(out, "property float y")?;
+        writeln!(out, "property float z")?;
+        writeln!(out, "element face {}", triangles.len())?;
+        writeln!(out, "property list uchar int vertex_index")?;
+        writeln!(out, "end_header")?;
+
+        for triangle in triangles {
+            for point in triangle.points {
+                let [x, y, z] = coords(&point);
+                writeln!(out, "{x} {y} {z}")?;
+            }
+        }
+
+        for (i, _) in triangles.iter().enumerate() {
+            let base = i * 3;
+            writeln!(out, "3 {} {} {}", base, base + 1, base + 2)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Error returned by [`export`] and [`export_with_format`]
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    /// I/O error while writing the export
+    #[error("I/O error while exporting model")]
+    Io(#[from] io::Error),
+
+    /// Error while writing the 3MF export
+    #[error("Error writing 3MF")]
+    ThreeMf(#[source] threemf::Error),
+
+    /// No export format could be inferred from the given path, and none was
+    /// given explicitly via `--format`
+    #[error("Could not infer an export format from `{}`", path.display())]
+    UnknownFormat {
+        /// The path that no format could be inferred from
+        path: PathBuf,
+    },
+
+    /// The format requested via `--format` doesn't match the one inferred
+    /// from the export path's extension
+    #[error(
+        "Requested export format `{requested:?}` doesn't match the format \
+         inferred from the export path (`{inferred:?}`)"
+    )]
+    FormatMismatch {
+        /// The format passed via `--format`
+        requested: ExportFormat,
+        /// The format inferred from the export path
+        inferred: ExportFormat,
+    },
+}
+
+impl Diagnostic for Error {
+    fn code(&self) -> &str {
+        match self {
+            Self::Io(_) => "fornjot::export_io_error",
+            Self::ThreeMf(_) => "fornjot::export_3mf_error",
+            Self::UnknownFormat { .. } => "fornjot::unknown_export_format",
+            Self::FormatMismatch { .. } => "fornjot::export_format_mismatch",
+        }
+    }
+
+    fn help(&self) -> Option<&str> {
+        match self {
+            Self::Io(_) | Self::ThreeMf(_) => None,
+            Self::UnknownFormat { .. } => Some(
+                "pass `--format <FORMAT>`, or use an export path with a recognized extension \
+                 (.stl, .obj, .3mf, .ply)",
+            ),
+            Self::FormatMismatch { .. } => {
+                Some("pass `--format` and an export path that agree, or drop one of them")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_extension_recognizes_known_extensions() {
+        assert_eq!(ExportFormat::from_extension(Path::new("model.stl")), Some(ExportFormat::Stl));
+        assert_eq!(ExportFormat::from_extension(Path::new("model.obj")), Some(ExportFormat::Obj));
+        assert_eq!(
+            ExportFormat::from_extension(Path::new("model.3mf")),
+            Some(ExportFormat::ThreeMf)
+        );
+        assert_eq!(ExportFormat::from_extension(Path::new("model.ply")), Some(ExportFormat::Ply));
+    }
+
+    #[test]
+    fn test_from_extension_is_case_insensitive() {
+        assert_eq!(ExportFormat::from_extension(Path::new("model.STL")), Some(ExportFormat::Stl));
+    }
+
+    #[test]
+    fn test_from_extension_unknown_extension_is_none() {
+        assert_eq!(ExportFormat::from_extension(Path::new("model.step")), None);
+    }
+
+    #[test]
+    fn test_from_extension_no_extension_is_none() {
+        assert_eq!(ExportFormat::from_extension(Path::new("model")), None);
+    }
+
+    #[test]
+    fn test_export_with_format_errors_on_unknown_format() {
+        let result = export_with_format(&[], Path::new("model.step"), None);
+        assert!(matches!(result, Err(Error::UnknownFormat { .. })));
+    }
+
+    #[test]
+    fn test_export_with_format_errors_on_format_mismatch() {
+        let result = export_with_format(&[], Path::new("model.stl"), Some(ExportFormat::Obj));
+        assert!(matches!(
+            result,
+            Err(Error::FormatMismatch {
+                requested: ExportFormat::Obj,
+                inferred: ExportFormat::Stl,
+            })
+        ));
+    }
+}
@@ -1,16 +1,20 @@
-use std::{error::Error as _, fmt};
-
 use fj_core::{
     Core,
     algorithms::{bounding_volume::BoundingVolume, triangulate::Triangulate},
-    validation::{ValidationConfig, ValidationErrors},
+    validation::{ValidationConfig, ValidationError, ValidationErrors},
 };
 use fj_interop::{InvalidTolerance, Tolerance};
 use fj_math::{Aabb, Point, Scalar};
 use fj_viewer::make_viewer_and_spawn_thread;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use crate::{Args, export, viewer};
+use crate::{
+    Args,
+    args::ValidationMode,
+    diagnostic::{Diagnostic, Severity},
+    export,
+    report::{Report, ResultExt},
+};
 
 /// An instance of Fornjot
 ///
@@ -63,11 +67,19 @@ impl Instance {
         for<'r> (&'r M, Tolerance): Triangulate,
         for<'r> &'r M: BoundingVolume<3>,
     {
-        self.init_logger()?;
+        let width = args.diagnostic_width;
+        self.process_model_inner(model, args)
+            .map_err(|report| report.with_width(width))
+    }
 
-        if !args.ignore_validation {
-            self.core.layers.validation.take_errors()?;
-        }
+    fn process_model_inner<M>(&mut self, model: &M, args: Args) -> Result
+    where
+        for<'r> (&'r M, Tolerance): Triangulate,
+        for<'r> &'r M: BoundingVolume<3>,
+    {
+        self.init_logger().change_context("initializing logger")?;
+
+        let summary = self.handle_validation(args.validation, args.max_errors)?;
 
         let aabb = model.aabb(&self.core.layers.geometry).unwrap_or(Aabb {
             min: Point::origin(),
@@ -79,15 +91,73 @@ impl Instance {
         let tri_mesh = (model, tolerance).triangulate(&mut self.core);
 
         if let Some(path) = args.export {
-            export::export(tri_mesh.all_triangles(), &path)?;
-            return Ok(());
+            let triangles: Vec<_> = tri_mesh.all_triangles().collect();
+            export::export_with_format(&triangles, &path, args.format)
+                .change_context(format!("exporting model to `{}`", path.display()))
+                .attach(aabb)
+                .attach(tolerance)?;
+            return Ok(summary);
         }
 
         make_viewer_and_spawn_thread(|viewer| {
             viewer.display_model(tri_mesh);
-        })?;
+        })
+        .change_context("displaying model")?;
 
-        Ok(())
+        Ok(summary)
+    }
+
+    /// Take the model's validation errors and handle them according to
+    /// `mode`, returning a [`ValidationSummary`] of what was tolerated.
+    ///
+    /// In [`ValidationMode::Strict`], validation errors abort, regardless of
+    /// their severity; the full [`ValidationErrors`] aggregate is reported,
+    /// not just the first issue encountered. In [`ValidationMode::Warn`],
+    /// issues are instead classified one by one (see
+    /// [`Diagnostic::severity`]) and, if marked fatal, still abort; `fj_core`
+    /// doesn't yet classify individual checks, though (see the `severity`
+    /// impl on [`ValidationError`] below), so in practice every issue is
+    /// currently tolerated and only `max_errors` can cut a `Warn` run short.
+    /// `max_errors` caps how many issues are processed before aborting
+    /// outright.
+    fn handle_validation(
+        &mut self,
+        mode: ValidationMode,
+        max_errors: Option<usize>,
+    ) -> std::result::Result<ValidationSummary, Report> {
+        if let ValidationMode::Off = mode {
+            return Ok(ValidationSummary::default());
+        }
+
+        let errors = match self.core.layers.validation.take_errors() {
+            Ok(()) => return Ok(ValidationSummary::default()),
+            Err(errors) => errors,
+        };
+
+        if reports_aggregate(mode) {
+            return Err(Report::new(errors).change_context("validating model"));
+        }
+
+        let mut summary = ValidationSummary::default();
+
+        for (i, error) in errors.into_iter().enumerate() {
+            match classify_warn_item(i, error.severity(), max_errors) {
+                WarnOutcome::MaxErrorsExceeded => {
+                    return Err(Report::new(error)
+                        .attach(summary)
+                        .change_context("validating model exceeded --max-errors"));
+                }
+                WarnOutcome::Fatal => {
+                    return Err(Report::new(error).attach(summary).change_context("validating model"));
+                }
+                WarnOutcome::Continue(severity) => {
+                    summary.count(severity);
+                    tracing::warn!(code = error.code(), "{error}");
+                }
+            }
+        }
+
+        Ok(summary)
     }
 
     /// Initialize the logger, if not already set.
@@ -107,83 +177,202 @@ impl Instance {
         &self,
         aabb: &Aabb<3>,
         user_defined: Option<Tolerance>,
-    ) -> std::result::Result<Tolerance, Error> {
+    ) -> std::result::Result<Tolerance, Report> {
         match user_defined {
             Some(tol) => Ok(tol),
             None => {
-                let mut min_extent = Scalar::MAX;
-                let mut found_nonzero = false;
-                for extent in aabb.size().components {
-                    if extent > Scalar::ZERO && extent < min_extent {
-                        min_extent = extent;
-                        found_nonzero = true;
-                    }
-                }
-                if !found_nonzero {
-                    return Err(Error::EmptyModel);
-                }
+                let Some(min_extent) = smallest_nonzero_extent(aabb.size().components) else {
+                    return Err(Report::new(EmptyModel).attach(*aabb));
+                };
                 let tolerance = min_extent / Scalar::from_f64(1000.);
-                Ok(Tolerance::from_scalar(tolerance)?)
+                Tolerance::from_scalar(tolerance)
+                    .attach(*aabb)
+                    .change_context("computing tolerance")
             }
         }
     }
 }
 
-/// Return value of [`Instance::process_model`]
-pub type Result = std::result::Result<(), Error>;
-
-/// Error returned by [`Instance::process_model`]
-#[derive(thiserror::Error)]
-pub enum Error {
-    /// Failed to set up logger
-    #[error("Failed to set up logger")]
-    Tracing(#[from] tracing::subscriber::SetGlobalDefaultError),
-
-    /// Error displaying model
-    #[error("Error displaying model")]
-    Display(#[from] viewer::Error),
-
-    /// Error exporting model
-    #[error("Error exporting model")]
-    Export(#[from] export::Error),
-
-    /// Invalid tolerance
-    #[error(transparent)]
-    Tolerance(#[from] InvalidTolerance),
-
-    /// Unhandled validation errors
-    #[error(transparent)]
-    Validation(#[from] ValidationErrors),
-
-    /// The model is empty or has zero size
-    #[error("The model is empty or has zero size; cannot compute tolerance")]
-    EmptyModel,
+/// The smallest nonzero extent among `extents`, or `None` if the model is
+/// degenerate (zero size in every dimension).
+fn smallest_nonzero_extent(extents: [Scalar; 3]) -> Option<Scalar> {
+    let mut min_extent = Scalar::MAX;
+    let mut found_nonzero = false;
+    for extent in extents {
+        if extent > Scalar::ZERO && extent < min_extent {
+            min_extent = extent;
+            found_nonzero = true;
+        }
+    }
+    found_nonzero.then_some(min_extent)
 }
 
-impl fmt::Debug for Error {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // When returning an error from Rust's `main` function, the runtime uses
-        // the error's `Debug` implementation to display it, not the `Display`
-        // one. This is unfortunate, and forces us to override `Debug` here.
+/// Whether `mode` reports the full [`ValidationErrors`] aggregate up front,
+/// rather than classifying issues one by one.
+fn reports_aggregate(mode: ValidationMode) -> bool {
+    mode == ValidationMode::Strict
+}
 
-        // We should be able to replace this with `Report`, once it is stable:
-        // https://doc.rust-lang.org/std/error/struct.Report.html
+/// What [`Instance::handle_validation`] should do with the warn-mode issue at
+/// index `i`, given its `severity` and the `--max-errors` cap.
+enum WarnOutcome {
+    /// Tolerate the issue and keep going, carrying its severity for counting.
+    Continue(Severity),
+    /// `--max-errors` has been exceeded; abort.
+    MaxErrorsExceeded,
+    /// The issue is fatal; abort.
+    Fatal,
+}
 
-        write!(f, "{self}")?;
+fn classify_warn_item(i: usize, severity: Severity, max_errors: Option<usize>) -> WarnOutcome {
+    if max_errors.is_some_and(|max_errors| i >= max_errors) {
+        WarnOutcome::MaxErrorsExceeded
+    } else if severity == Severity::Error {
+        WarnOutcome::Fatal
+    } else {
+        WarnOutcome::Continue(severity)
+    }
+}
 
-        let mut source = self.source();
+/// Return value of [`Instance::process_model`]
+pub type Result = std::result::Result<ValidationSummary, Report>;
 
-        if source.is_some() {
-            write!(f, "\n\nCaused by:")?;
-        }
+/// Counts of validation issues tolerated during a `--validation warn` run.
+///
+/// Returned on success so that scripts driving Fornjot can tell how many
+/// issues were swept under the rug during an export.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ValidationSummary {
+    /// Non-fatal issues that were logged and tolerated
+    pub warnings: usize,
+    /// Informational issues that were logged and tolerated
+    pub advisories: usize,
+}
 
-        let mut i = 0;
-        while let Some(s) = source {
-            write!(f, "\n    {i}: {s}")?;
-            source = s.source();
-            i += 1;
+impl ValidationSummary {
+    fn count(&mut self, severity: Severity) {
+        match severity {
+            Severity::Warning => self.warnings += 1,
+            Severity::Advice => self.advisories += 1,
+            // Fatal issues abort validation instead of being counted here.
+            Severity::Error => {}
         }
+    }
+}
 
-        Ok(())
+/// The model has no extent in any dimension, so no tolerance can be inferred
+/// for it.
+#[derive(Debug, thiserror::Error)]
+#[error("The model is empty or has zero size; cannot compute tolerance")]
+pub struct EmptyModel;
+
+impl Diagnostic for EmptyModel {
+    fn code(&self) -> &str {
+        "fornjot::empty_model"
+    }
+
+    fn help(&self) -> Option<&str> {
+        Some("pass --tolerance, or give the model non-zero extent")
+    }
+}
+
+impl Diagnostic for InvalidTolerance {
+    fn code(&self) -> &str {
+        "fornjot::invalid_tolerance"
+    }
+
+    fn help(&self) -> Option<&str> {
+        Some("pass a tolerance greater than zero")
+    }
+}
+
+impl Diagnostic for ValidationErrors {
+    fn code(&self) -> &str {
+        "fornjot::validation_failed"
+    }
+
+    fn help(&self) -> Option<&str> {
+        Some("pass --validation warn or --validation off to export or display the model anyway")
+    }
+}
+
+impl Diagnostic for ValidationError {
+    fn code(&self) -> &str {
+        "fornjot::validation_error"
+    }
+
+    fn help(&self) -> Option<&str> {
+        Some("pass --validation warn or --validation off to tolerate this issue")
+    }
+
+    fn severity(&self) -> Severity {
+        // Until individual checks in `fj_core` classify themselves, treat
+        // every validation issue as non-fatal by default; `--validation
+        // strict` still aborts on any of them regardless of this.
+        Severity::Warning
+    }
+}
+
+impl Diagnostic for tracing::subscriber::SetGlobalDefaultError {
+    fn code(&self) -> &str {
+        "fornjot::logger_init_failed"
+    }
+}
+
+impl Diagnostic for fj_viewer::Error {
+    fn code(&self) -> &str {
+        "fornjot::display_failed"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smallest_nonzero_extent_picks_the_smallest_positive_value() {
+        let extents = [Scalar::from_f64(3.0), Scalar::from_f64(1.0), Scalar::from_f64(2.0)];
+        assert_eq!(smallest_nonzero_extent(extents), Some(Scalar::from_f64(1.0)));
+    }
+
+    #[test]
+    fn test_smallest_nonzero_extent_ignores_zero_extents() {
+        let extents = [Scalar::ZERO, Scalar::from_f64(5.0), Scalar::ZERO];
+        assert_eq!(smallest_nonzero_extent(extents), Some(Scalar::from_f64(5.0)));
+    }
+
+    #[test]
+    fn test_smallest_nonzero_extent_is_none_for_degenerate_model() {
+        assert_eq!(smallest_nonzero_extent([Scalar::ZERO; 3]), None);
+    }
+
+    #[test]
+    fn test_reports_aggregate_only_in_strict_mode() {
+        assert!(reports_aggregate(ValidationMode::Strict));
+        assert!(!reports_aggregate(ValidationMode::Warn));
+        assert!(!reports_aggregate(ValidationMode::Off));
+    }
+
+    #[test]
+    fn test_classify_warn_item_continues_below_max_errors_and_non_fatal() {
+        assert!(matches!(
+            classify_warn_item(0, Severity::Warning, Some(5)),
+            WarnOutcome::Continue(Severity::Warning)
+        ));
+    }
+
+    #[test]
+    fn test_classify_warn_item_aborts_past_max_errors() {
+        assert!(matches!(classify_warn_item(5, Severity::Warning, Some(5)), WarnOutcome::MaxErrorsExceeded));
+    }
+
+    #[test]
+    fn test_classify_warn_item_aborts_on_fatal_severity() {
+        assert!(matches!(classify_warn_item(0, Severity::Error, None), WarnOutcome::Fatal));
+    }
+
+    #[test]
+    fn test_classify_warn_item_max_errors_takes_priority_over_fatal() {
+        assert!(matches!(classify_warn_item(5, Severity::Error, Some(5)), WarnOutcome::MaxErrorsExceeded));
     }
 }
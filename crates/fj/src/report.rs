@@ -0,0 +1,272 @@
+use std::fmt;
+
+use crate::diagnostic::{self, Diagnostic, Severity, Snippet};
+
+/// A diagnostic report, accumulating context as an error travels up the call
+/// stack.
+///
+/// Inspired by the Context/Attachment model of the `error-stack` crate: the
+/// report is a stack of [`Frame`]s, each one either a *context* describing
+/// the operation that was in progress, or an *attachment* carrying some
+/// piece of data that was relevant at that point (a computed bounding box,
+/// the chosen tolerance, the export path, ...). Frames are pushed as the
+/// error is handed back up through `?`, so the stack reads, from the
+/// outermost call site to the original failure, like a breadcrumb trail.
+///
+/// The report also carries the [`Diagnostic`] metadata (code, help,
+/// severity, and an optional snippet) of the error it was created from; that
+/// metadata describes the root cause and isn't affected by later
+/// [`change_context`](Report::change_context) calls.
+pub struct Report {
+    code: String,
+    help: Option<String>,
+    severity: Severity,
+    snippet: Option<Snippet>,
+    width: usize,
+    frames: Vec<Frame>,
+}
+
+enum Frame {
+    Context(Box<dyn fmt::Display + Send + Sync>),
+    Attachment(Box<dyn fmt::Debug + Send + Sync>),
+}
+
+/// Default wrap width, used until [`Report::with_width`] narrows it, e.g. to
+/// the detected terminal width.
+const DEFAULT_WIDTH: usize = 100;
+
+impl Report {
+    /// Start a new report from a [`Diagnostic`] error.
+    pub fn new<E>(error: E) -> Self
+    where
+        E: Diagnostic + 'static,
+    {
+        Self {
+            code: error.code().to_owned(),
+            help: error.help().map(str::to_owned),
+            severity: error.severity(),
+            snippet: error.snippet(),
+            width: DEFAULT_WIDTH,
+            frames: vec![Frame::Context(Box::new(error.to_string()))],
+        }
+    }
+
+    /// Push a new context frame, describing the operation that was in
+    /// progress when this report was handed further up the call stack.
+    #[must_use]
+    pub fn change_context<C>(mut self, context: C) -> Self
+    where
+        C: fmt::Display + Send + Sync + 'static,
+    {
+        self.frames.push(Frame::Context(Box::new(context)));
+        self
+    }
+
+    /// Attach a piece of data that was relevant to the most recent context.
+    #[must_use]
+    pub fn attach<A>(mut self, attachment: A) -> Self
+    where
+        A: fmt::Debug + Send + Sync + 'static,
+    {
+        self.frames.push(Frame::Attachment(Box::new(attachment)));
+        self
+    }
+
+    /// Set the column width the rendered report should be wrapped to.
+    #[must_use]
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = width;
+        self
+    }
+
+    fn groups(&self) -> Vec<(&dyn fmt::Display, Vec<&dyn fmt::Debug>)> {
+        let mut groups: Vec<(&dyn fmt::Display, Vec<&dyn fmt::Debug>)> = Vec::new();
+
+        for frame in &self.frames {
+            match frame {
+                Frame::Context(context) => groups.push((context.as_ref(), Vec::new())),
+                Frame::Attachment(attachment) => {
+                    if let Some((_, attachments)) = groups.last_mut() {
+                        attachments.push(attachment.as_ref());
+                    }
+                }
+            }
+        }
+
+        groups
+    }
+
+    fn cause_chain(&self) -> String {
+        let mut rendered = String::new();
+
+        for (i, (context, attachments)) in self.groups().into_iter().rev().enumerate() {
+            if i > 0 {
+                rendered.push('\n');
+            }
+            rendered.push_str(&context.to_string());
+            for attachment in attachments {
+                rendered.push_str(&format!("\n    - {attachment:?}"));
+            }
+        }
+
+        rendered
+    }
+}
+
+impl<E> From<E> for Report
+where
+    E: Diagnostic + 'static,
+{
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{}: {}",
+            diagnostic::wrap(&format!("{}[{}]", self.severity, self.code), self.width),
+            diagnostic::wrap(&self.cause_chain(), self.width)
+        )?;
+
+        if let Some(snippet) = &self.snippet {
+            writeln!(f, "{snippet}")?;
+        }
+
+        if let Some(help) = &self.help {
+            write!(f, "help: {}", diagnostic::wrap(help, self.width))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // When returning an error from Rust's `main` function, the runtime
+        // uses the error's `Debug` implementation to display it, not the
+        // `Display` one. This is unfortunate, and forces us to override
+        // `Debug` here.
+        write!(f, "{self}")
+    }
+}
+
+/// Extension trait for enriching a [`Result`]'s error with diagnostic
+/// context as it passes through a `?` site.
+pub trait ResultExt<T> {
+    /// Describe the operation that was in progress when this result was
+    /// produced, turning the error into a [`Report`] (or extending an
+    /// existing one).
+    fn change_context<C>(self, context: C) -> Result<T, Report>
+    where
+        C: fmt::Display + Send + Sync + 'static;
+
+    /// Attach a piece of data that was relevant to the current context.
+    fn attach<A>(self, attachment: A) -> Result<T, Report>
+    where
+        A: fmt::Debug + Send + Sync + 'static;
+}
+
+impl<T, E> ResultExt<T> for Result<T, E>
+where
+    E: Into<Report>,
+{
+    fn change_context<C>(self, context: C) -> Result<T, Report>
+    where
+        C: fmt::Display + Send + Sync + 'static,
+    {
+        self.map_err(|err| err.into().change_context(context))
+    }
+
+    fn attach<A>(self, attachment: A) -> Result<T, Report>
+    where
+        A: fmt::Debug + Send + Sync + 'static,
+    {
+        self.map_err(|err| err.into().attach(attachment))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, thiserror::Error)]
+    #[error("boom")]
+    struct TestError;
+
+    impl Diagnostic for TestError {
+        fn code(&self) -> &str {
+            "test::boom"
+        }
+
+        fn help(&self) -> Option<&str> {
+            Some("try again")
+        }
+    }
+
+    #[test]
+    fn test_report_new_carries_diagnostic_metadata() {
+        let report = Report::new(TestError);
+        assert_eq!(report.code, "test::boom");
+        assert_eq!(report.help.as_deref(), Some("try again"));
+        assert_eq!(report.severity, Severity::Error);
+    }
+
+    #[test]
+    fn test_report_display_includes_code_message_and_help() {
+        let rendered = Report::new(TestError).to_string();
+        assert!(rendered.contains("test::boom"));
+        assert!(rendered.contains("boom"));
+        assert!(rendered.contains("try again"));
+    }
+
+    #[test]
+    fn test_report_groups_attachments_under_their_context_most_recent_first() {
+        let report = Report::new(TestError)
+            .attach(1u32)
+            .change_context("outer operation")
+            .attach(2u32);
+
+        let chain = report.cause_chain();
+        let outer_idx = chain.find("outer operation").unwrap();
+        let boom_idx = chain.find("boom").unwrap();
+        let one_idx = chain.find("- 1").unwrap();
+        let two_idx = chain.find("- 2").unwrap();
+
+        // The most recently added context comes first, with its own
+        // attachment nested beneath it, followed by the original context and
+        // the attachment that belonged to it.
+        assert!(outer_idx < two_idx);
+        assert!(two_idx < boom_idx);
+        assert!(boom_idx < one_idx);
+    }
+
+    #[test]
+    fn test_report_with_width_narrows_wrapping() {
+        let report = || {
+            Report::new(TestError)
+                .change_context("one two three four five six seven eight nine ten")
+        };
+
+        let wide = report().to_string();
+        let narrow = report().with_width(10).to_string();
+
+        assert!(narrow.lines().count() > wide.lines().count());
+    }
+
+    #[test]
+    fn test_result_ext_change_context_wraps_error() {
+        let result: Result<(), TestError> = Err(TestError);
+        let report = result.change_context("doing something").unwrap_err();
+        assert!(report.cause_chain().contains("doing something"));
+    }
+
+    #[test]
+    fn test_result_ext_attach_adds_to_latest_context() {
+        let result: Result<(), TestError> = Err(TestError);
+        let report = result.attach(42u32).unwrap_err();
+        assert!(report.cause_chain().contains("- 42"));
+    }
+}